@@ -0,0 +1 @@
+pub mod pseudo_terminal;
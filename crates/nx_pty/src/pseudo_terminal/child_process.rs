@@ -0,0 +1,293 @@
+use std::io::{Read, Write};
+use std::process::{ChildStderr, ChildStdin, ChildStdout};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use napi::bindgen_prelude::Buffer;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use super::ipc;
+use super::transport::SocketGuard;
+
+/// Which channel a [`ChildProcess`]'s control/IPC traffic flows over.
+#[derive(Debug, Clone)]
+pub enum Transport {
+    /// Traffic is multiplexed over the pty's stdio, the historical default.
+    Stdio,
+    /// Traffic flows over a dedicated local socket (Unix domain socket path, or Windows named
+    /// pipe path), leaving the child's own stdin/stdout/stderr free.
+    Socket(String),
+}
+
+impl Transport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transport::Stdio => "stdio",
+            Transport::Socket(_) => "socket",
+        }
+    }
+}
+
+/// A process spawned by [`super::run_command`] or [`super::fork_with_channel`].
+#[napi]
+pub struct ChildProcess {
+    pid: u32,
+    transport: Transport,
+    to_child: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    from_child: Option<Box<dyn Read + Send>>,
+    stdin_pipe: Option<Arc<Mutex<ChildStdin>>>,
+    stdout_pipe: Option<ChildStdout>,
+    stderr_pipe: Option<ChildStderr>,
+    // Keeps a socket-transport listener bound for as long as this `ChildProcess` is alive; unused
+    // otherwise.
+    #[allow(dead_code)]
+    socket_guard: Option<SocketGuard>,
+    #[cfg(unix)]
+    pty_slave: Option<std::os::fd::RawFd>,
+    #[cfg(unix)]
+    parent_pgrp: Option<libc::pid_t>,
+}
+
+impl ChildProcess {
+    pub(crate) fn new(pid: u32) -> Self {
+        Self {
+            pid,
+            transport: Transport::Stdio,
+            to_child: None,
+            from_child: None,
+            stdin_pipe: None,
+            stdout_pipe: None,
+            stderr_pipe: None,
+            socket_guard: None,
+            #[cfg(unix)]
+            pty_slave: None,
+            #[cfg(unix)]
+            parent_pgrp: None,
+        }
+    }
+
+    /// Wires the duplex IPC channel established at fork time over a pair of unnamed pipes.
+    pub(crate) fn with_ipc_channel(
+        mut self,
+        to_child: std::fs::File,
+        from_child: std::fs::File,
+    ) -> Self {
+        self.to_child = Some(Arc::new(Mutex::new(Box::new(to_child))));
+        self.from_child = Some(Box::new(from_child));
+        self
+    }
+
+    /// Wires the same duplex IPC channel as [`Self::with_ipc_channel`], but over an accepted
+    /// local-socket connection instead of a pair of pipes: `stream` is cloned so `send` and
+    /// `on_message` can use independent read/write halves, same as the pipe-backed channel does.
+    /// A no-op, leaving this `ChildProcess` without an IPC channel, if the clone fails.
+    #[cfg(unix)]
+    pub(crate) fn with_ipc_stream(mut self, stream: std::os::unix::net::UnixStream) -> Self {
+        if let Ok(read_half) = stream.try_clone() {
+            self.to_child = Some(Arc::new(Mutex::new(Box::new(stream))));
+            self.from_child = Some(Box::new(read_half));
+        }
+        self
+    }
+
+    pub(crate) fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Keeps `guard`'s socket listener bound for as long as this `ChildProcess` is alive.
+    pub(crate) fn with_socket_guard(mut self, guard: Option<SocketGuard>) -> Self {
+        self.socket_guard = guard;
+        self
+    }
+
+    /// Attaches the piped ends of a spawned child's streams, whichever of the three were
+    /// configured as [`super::stdio::StdioDisposition::Piped`].
+    pub(crate) fn with_piped_stdio(
+        mut self,
+        stdin: Option<ChildStdin>,
+        stdout: Option<ChildStdout>,
+        stderr: Option<ChildStderr>,
+    ) -> Self {
+        self.stdin_pipe = stdin.map(|s| Arc::new(Mutex::new(s)));
+        self.stdout_pipe = stdout;
+        self.stderr_pipe = stderr;
+        self
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn with_pty_slave(mut self, slave: std::os::fd::RawFd) -> Self {
+        self.pty_slave = Some(slave);
+        self
+    }
+}
+
+#[napi]
+impl ChildProcess {
+    #[napi(getter)]
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Which channel this child's control/IPC traffic is using: `"stdio"` or `"socket"`. On Unix,
+    /// a `"socket"` transport keeps its listener bound (and cleans up the path) for as long as
+    /// this `ChildProcess` is alive; the child's connection to `NX_PTY_SOCKET_PATH` is accepted
+    /// at spawn time and becomes the channel behind [`Self::send`]/[`Self::on_message`]. A child
+    /// that doesn't connect in time still reports `"socket"`, with no IPC channel.
+    #[napi(getter)]
+    pub fn transport(&self) -> String {
+        self.transport.as_str().to_string()
+    }
+
+    /// Sends one length-prefixed message to the child over the duplex IPC channel established at
+    /// fork time. `id` is accepted for parity with the legacy fake-IPC message envelope but is
+    /// otherwise unused here; framing alone is enough to reassemble messages on the other end.
+    /// A no-op (returns `Ok`) for children that weren't forked with a channel.
+    #[napi]
+    pub fn send(&self, _id: String, bytes: Buffer) -> napi::Result<()> {
+        let Some(to_child) = &self.to_child else {
+            return Ok(());
+        };
+        let mut to_child = to_child.lock().unwrap();
+        ipc::write_frame(&mut *to_child, bytes.as_ref())
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    /// Registers `callback` to be invoked with each frame the child writes to the duplex IPC
+    /// channel. `id` is accepted for parity with [`Self::send`]'s legacy envelope but is
+    /// otherwise unused here; there's only one channel per `ChildProcess`, so it doesn't
+    /// disambiguate anything yet. Runs the read loop on a dedicated thread, which exits cleanly
+    /// once the child closes its end of the pipe (i.e. on child exit). Only one callback may be
+    /// registered; the channel's read end is consumed on the first call.
+    #[napi]
+    pub fn on_message(
+        &mut self,
+        _id: String,
+        callback: ThreadsafeFunction<Buffer>,
+    ) -> napi::Result<()> {
+        let Some(mut from_child) = self.from_child.take() else {
+            return Ok(());
+        };
+        thread::spawn(move || loop {
+            match ipc::read_frame(&mut from_child) {
+                Ok(Some(payload)) => {
+                    callback.call(Ok(payload.into()), ThreadsafeFunctionCallMode::Blocking);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        });
+        Ok(())
+    }
+
+    /// Writes to the child's stdin. A no-op (returns `Ok`) unless stdin was configured as
+    /// [`super::stdio::StdioDisposition::Piped`].
+    #[napi]
+    pub fn write_stdin(&self, bytes: Buffer) -> napi::Result<()> {
+        let Some(stdin) = &self.stdin_pipe else {
+            return Ok(());
+        };
+        let mut stdin = stdin.lock().unwrap();
+        stdin
+            .write_all(bytes.as_ref())
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    /// Registers `callback` to be invoked with each chunk read from the child's stdout. A no-op
+    /// unless stdout was configured as [`super::stdio::StdioDisposition::Piped`]; the pipe is
+    /// consumed on the first call.
+    #[napi]
+    pub fn on_stdout(&mut self, callback: ThreadsafeFunction<Buffer>) -> napi::Result<()> {
+        spawn_raw_read_loop(self.stdout_pipe.take(), callback);
+        Ok(())
+    }
+
+    /// Registers `callback` to be invoked with each chunk read from the child's stderr. A no-op
+    /// unless stderr was configured as [`super::stdio::StdioDisposition::Piped`]; the pipe is
+    /// consumed on the first call.
+    #[napi]
+    pub fn on_stderr(&mut self, callback: ThreadsafeFunction<Buffer>) -> napi::Result<()> {
+        spawn_raw_read_loop(self.stderr_pipe.take(), callback);
+        Ok(())
+    }
+
+    /// Moves this child's process group into (`true`) or out of (`false`) the foreground of the
+    /// pty, so it can read directly from the controlling terminal (editors, prompts, REPLs). The
+    /// parent's process group is recorded the first time this is called with `true` and restored
+    /// automatically on an explicit call with `false`, or when this `ChildProcess` is dropped —
+    /// there's no exit/SIGCHLD hook, so a child that exits while still in the foreground only
+    /// gets the parent's group restored once the `ChildProcess` itself is dropped. A no-op that
+    /// always returns `Ok` on Windows and for children with no pty.
+    #[napi]
+    pub fn set_foreground(&mut self, foreground: bool) -> napi::Result<()> {
+        #[cfg(unix)]
+        {
+            let Some(slave) = self.pty_slave else {
+                return Ok(());
+            };
+            if foreground {
+                if self.parent_pgrp.is_none() {
+                    self.parent_pgrp = Some(unsafe { libc::getpgrp() });
+                }
+                tcsetpgrp(slave, self.pid as libc::pid_t)
+            } else {
+                let parent_pgrp = self
+                    .parent_pgrp
+                    .unwrap_or_else(|| unsafe { libc::getpgrp() });
+                tcsetpgrp(slave, parent_pgrp)
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = foreground;
+            Ok(())
+        }
+    }
+}
+
+fn spawn_raw_read_loop<R: Read + Send + 'static>(
+    reader: Option<R>,
+    callback: ThreadsafeFunction<Buffer>,
+) {
+    let Some(mut reader) = reader else {
+        return;
+    };
+    thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    callback.call(
+                        Ok(buf[..n].to_vec().into()),
+                        ThreadsafeFunctionCallMode::Blocking,
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// `fd` must be the pty's slave, not its master: `tcsetpgrp` requires a descriptor referring to
+/// the calling process's controlling terminal, and on Linux that check resolves a pty master
+/// back to its linked slave's tty anyway, so operating on the slave directly is both the
+/// portable and the documented choice (the sibling `EchoGuard` in shell_session.rs likewise
+/// operates on the slave, for termios).
+#[cfg(unix)]
+fn tcsetpgrp(slave: std::os::fd::RawFd, pgrp: libc::pid_t) -> napi::Result<()> {
+    if unsafe { libc::tcsetpgrp(slave, pgrp) } != 0 {
+        return Err(napi::Error::from_reason(
+            std::io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+impl Drop for ChildProcess {
+    fn drop(&mut self) {
+        if let (Some(slave), Some(parent_pgrp)) = (self.pty_slave, self.parent_pgrp) {
+            let _ = tcsetpgrp(slave, parent_pgrp);
+        }
+    }
+}
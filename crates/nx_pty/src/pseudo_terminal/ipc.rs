@@ -0,0 +1,171 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::Command;
+
+/// Creates the two unnamed pipes backing a [`super::fork_with_channel`] duplex connection,
+/// wires the child's ends into `cmd`'s environment as `NX_PTY_IPC_READ_FD`/`NX_PTY_IPC_WRITE_FD`,
+/// and returns the parent's write/read ends alongside the child's raw descriptors so the caller
+/// can close the parent's copy of those once `cmd` has been spawned.
+#[cfg(unix)]
+pub fn attach_ipc_channel(cmd: &mut Command) -> napi::Result<(File, File, [i32; 2])> {
+    use std::os::fd::FromRawFd;
+
+    let (parent_read, child_write) = create_inheritable_pipe()?;
+    let (child_read, parent_write) = create_inheritable_pipe()?;
+
+    // Only the child's ends should survive the exec. Without this, the child inherits its own
+    // copy of parent_write too, so closing the parent's end is never enough for the child to see
+    // EOF on the parent -> child pipe.
+    set_cloexec(parent_read)?;
+    set_cloexec(parent_write)?;
+
+    cmd.env("NX_PTY_IPC_READ_FD", child_read.to_string());
+    cmd.env("NX_PTY_IPC_WRITE_FD", child_write.to_string());
+
+    // SAFETY: `create_inheritable_pipe` just handed us these as freshly opened, uniquely owned
+    // pipe ends.
+    let parent_write = unsafe { File::from_raw_fd(parent_write) };
+    let parent_read = unsafe { File::from_raw_fd(parent_read) };
+
+    Ok((parent_write, parent_read, [child_read, child_write]))
+}
+
+#[cfg(not(unix))]
+pub fn attach_ipc_channel(_cmd: &mut Command) -> napi::Result<(File, File, [i32; 2])> {
+    Err(napi::Error::from_reason(
+        "typed duplex fork channels are not yet supported on this platform".to_string(),
+    ))
+}
+
+/// Closes the parent process's copy of the child's pipe descriptors once the child has been
+/// forked, so the parent doesn't hold the write end of its own read pipe (or vice versa) open
+/// forever. A no-op on platforms where [`attach_ipc_channel`] never hands out descriptors.
+#[cfg(unix)]
+pub fn close_parent_copy_of_child_fds(fds: [i32; 2]) {
+    for fd in fds {
+        unsafe {
+            libc::close(fd);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn close_parent_copy_of_child_fds(_fds: [i32; 2]) {}
+
+#[cfg(unix)]
+fn create_inheritable_pipe() -> napi::Result<(i32, i32)> {
+    let mut fds = [0i32; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(napi::Error::from_reason(
+            io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok((fds[0], fds[1]))
+}
+
+/// Marks `fd` close-on-exec, so it doesn't survive into a child process across `exec`.
+#[cfg(unix)]
+fn set_cloexec(fd: i32) -> napi::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    if flags == -1 {
+        return Err(napi::Error::from_reason(
+            io::Error::last_os_error().to_string(),
+        ));
+    }
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) } == -1 {
+        return Err(napi::Error::from_reason(
+            io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Writes a single length-prefixed frame: a u32 big-endian length followed by `payload`.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame payload too large"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Reads a single length-prefixed frame written by [`write_frame`].
+///
+/// Returns `Ok(None)` on a clean EOF between frames (i.e. the child exited), and an error for
+/// any other I/O failure, including an EOF that lands in the middle of a frame.
+pub fn read_frame<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len_buf = [0u8; 4];
+    match read_exact_or_eof(reader, &mut len_buf)? {
+        false => return Ok(None),
+        true => {}
+    }
+
+    let len = u32::from_be_bytes(len_buf) as usize;
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// Like [`Read::read_exact`], but treats an EOF on the very first byte as "no more frames"
+/// rather than an error, so the reader loop can tell a clean child exit apart from a
+/// truncated frame.
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => {
+                return if read == 0 {
+                    Ok(false)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "eof in the middle of a frame",
+                    ))
+                };
+            }
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_a_frame() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, b"hello").unwrap();
+
+        let mut cursor = io::Cursor::new(buf);
+        assert_eq!(read_frame(&mut cursor).unwrap(), Some(b"hello".to_vec()));
+    }
+
+    #[test]
+    fn reassembles_a_frame_split_across_reads() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, b"partial-read-payload").unwrap();
+
+        struct OneByteAtATime(io::Cursor<Vec<u8>>);
+        impl Read for OneByteAtATime {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.read(&mut buf[..1.min(buf.len())])
+            }
+        }
+
+        let mut reader = OneByteAtATime(io::Cursor::new(buf));
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some(b"partial-read-payload".to_vec())
+        );
+    }
+
+    #[test]
+    fn clean_eof_between_frames_returns_none() {
+        let mut cursor = io::Cursor::new(Vec::new());
+        assert_eq!(read_frame(&mut cursor).unwrap(), None);
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+pub mod child_process;
+pub mod ipc;
+pub mod os;
+pub mod pty;
+pub mod shell_session;
+pub mod stdio;
+pub mod transport;
+
+use child_process::ChildProcess;
+use stdio::StdioConfig;
+use transport::SocketGuard;
+
+/// How long [`run_command_with_transport`]/[`fork_with_transport`] wait for a child to dial back
+/// into its local socket before giving up on wiring an IPC channel for it (the child process
+/// itself still runs either way).
+const SOCKET_ACCEPT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub struct PseudoTerminal {
+    #[cfg(unix)]
+    pty: pty::Pty,
+}
+
+impl PseudoTerminal {
+    #[cfg(unix)]
+    fn slave_fd(&self) -> std::os::fd::RawFd {
+        self.pty.slave
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn pty(&self) -> &pty::Pty {
+        &self.pty
+    }
+}
+
+#[cfg(unix)]
+pub fn create_pseudo_terminal() -> napi::Result<PseudoTerminal> {
+    let pty = pty::open().map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    Ok(PseudoTerminal { pty })
+}
+
+#[cfg(not(unix))]
+pub fn create_pseudo_terminal() -> napi::Result<PseudoTerminal> {
+    Ok(PseudoTerminal {})
+}
+
+pub fn run_command(
+    pseudo_terminal: &PseudoTerminal,
+    command: String,
+    command_dir: Option<String>,
+    js_env: Option<HashMap<String, String>>,
+    exec_argv: Option<Vec<String>>,
+    quiet: Option<bool>,
+    tty: Option<bool>,
+) -> napi::Result<ChildProcess> {
+    run_command_with_transport(
+        pseudo_terminal,
+        command,
+        command_dir,
+        js_env,
+        exec_argv,
+        quiet,
+        tty,
+        None,
+        None,
+    )
+}
+
+/// Like [`run_command`], but when `local_socket` is `true` attempts to route control/IPC
+/// traffic over a per-process local socket instead of stdio. A socket that actually binds wins
+/// over the pty/`stdio` wiring entirely, leaving the child's own stdin/stdout/stderr at
+/// `Command`'s default (inherited) disposition so it's genuinely free, e.g. for a task that draws
+/// its own terminal UI; the socket itself carries the IPC traffic once the child dials back into
+/// `NX_PTY_SOCKET_PATH` (see [`transport::SocketGuard::accept`]). Transparently falls back to the
+/// stdio transport if socket binding fails; [`ChildProcess::transport`] reports which one won. A
+/// child that never connects within [`SOCKET_ACCEPT_TIMEOUT`] keeps the socket transport, with no
+/// IPC channel wired up.
+///
+/// `stdio`, when given, independently sets each of stdin/stdout/stderr to
+/// [`stdio::StdioDisposition::Inherit`]/`Piped`/`Null` instead of wiring all three through the
+/// pty, and replaces `tty`/`quiet` entirely (unless a socket wins, per above): the pty is not
+/// used, and any stream it leaves unset is inherited; `Piped` streams come back as handles on
+/// [`ChildProcess`]
+/// (`write_stdin`/`on_stdout`/`on_stderr`). Omitting it keeps the historical behavior: `tty`
+/// (default `true`) runs the command on the pty, and with `tty: false`, `quiet: true` maps onto
+/// stdout/stderr going to the null device.
+#[allow(clippy::too_many_arguments)]
+pub fn run_command_with_transport(
+    pseudo_terminal: &PseudoTerminal,
+    command: String,
+    command_dir: Option<String>,
+    js_env: Option<HashMap<String, String>>,
+    exec_argv: Option<Vec<String>>,
+    quiet: Option<bool>,
+    tty: Option<bool>,
+    local_socket: Option<bool>,
+    stdio: Option<StdioConfig>,
+) -> napi::Result<ChildProcess> {
+    let mut cmd = build_command(
+        &command,
+        command_dir.as_deref(),
+        js_env.as_ref(),
+        exec_argv.as_ref(),
+    );
+
+    let (transport, socket_guard) =
+        transport::attach(&mut cmd, &command, local_socket.unwrap_or(false));
+    let socket_active = socket_guard.is_some();
+
+    let use_pty = !socket_active && stdio.is_none() && tty.unwrap_or(true);
+    // A live socket's whole point is to free the child's stdio, so leave `cmd`'s streams at the
+    // default (inherited) disposition in that case instead of wiring them through the pty.
+    if use_pty {
+        attach_pty(&mut cmd, pseudo_terminal);
+    } else if !socket_active {
+        let stdio = stdio.unwrap_or_else(|| stdio::from_quiet(quiet));
+        stdio::apply(&mut cmd, &stdio);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+    let mut child_process = ChildProcess::new(child.id())
+        .with_transport(transport)
+        .with_piped_stdio(child.stdin.take(), child.stdout.take(), child.stderr.take());
+    child_process = connect_socket_channel(child_process, &socket_guard);
+    child_process = child_process.with_socket_guard(socket_guard);
+    if use_pty {
+        child_process = with_pty_slave(child_process, pseudo_terminal);
+    }
+    Ok(child_process)
+}
+
+/// Forks `command` (a `node <fork_script> ...` invocation) with a typed, length-framed duplex
+/// channel to the child in place of the legacy file-based fake IPC shim: two unnamed pipes are
+/// created before spawning and their descriptors are handed to the child through environment
+/// variables rather than a path on disk, so the reassembled messages come through
+/// [`ChildProcess::on_message`]/[`ChildProcess::send`] instead of Node's emulated `process.send`.
+pub fn fork_with_channel(
+    pseudo_terminal: &PseudoTerminal,
+    command: String,
+    command_dir: Option<String>,
+    js_env: Option<HashMap<String, String>>,
+    exec_argv: Option<Vec<String>>,
+) -> napi::Result<ChildProcess> {
+    fork_with_transport(
+        pseudo_terminal,
+        command,
+        command_dir,
+        js_env,
+        exec_argv,
+        None,
+    )
+}
+
+/// Like [`fork_with_channel`], but when `local_socket` is `true` routes the fork's control
+/// traffic over a local socket instead of the unnamed-pipe duplex channel. A socket that actually
+/// binds wins over the pty entirely, same as in [`run_command_with_transport`]: the point of
+/// asking for one is to leave the forked child's stdio free, so in that case `cmd`'s streams are
+/// left at the default (inherited) disposition and the pty is never attached. Falls back to the
+/// pty + pipe-channel path, same as `local_socket: false`, if binding fails.
+#[allow(clippy::too_many_arguments)]
+pub fn fork_with_transport(
+    pseudo_terminal: &PseudoTerminal,
+    command: String,
+    command_dir: Option<String>,
+    js_env: Option<HashMap<String, String>>,
+    exec_argv: Option<Vec<String>>,
+    local_socket: Option<bool>,
+) -> napi::Result<ChildProcess> {
+    let mut cmd = build_command(
+        &command,
+        command_dir.as_deref(),
+        js_env.as_ref(),
+        exec_argv.as_ref(),
+    );
+
+    if local_socket.unwrap_or(false) {
+        let (transport, socket_guard) = transport::attach(&mut cmd, &command, true);
+        if socket_guard.is_some() {
+            let child = cmd
+                .spawn()
+                .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+            let mut child_process = ChildProcess::new(child.id()).with_transport(transport);
+            child_process = connect_socket_channel(child_process, &socket_guard);
+            child_process = child_process.with_socket_guard(socket_guard);
+            return Ok(child_process);
+        }
+        // Binding failed: `transport::attach` already fell back to `Transport::Stdio` without
+        // touching `cmd`'s env, so fall through to the pty + pipe-channel path below, same as if
+        // `local_socket` had been `false`.
+    }
+
+    attach_pty(&mut cmd, pseudo_terminal);
+
+    let (parent_write, parent_read, child_fds) = ipc::attach_ipc_channel(&mut cmd)?;
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| napi::Error::from_reason(e.to_string()));
+    ipc::close_parent_copy_of_child_fds(child_fds);
+    let child = child?;
+
+    let child_process = ChildProcess::new(child.id()).with_ipc_channel(parent_write, parent_read);
+    Ok(with_pty_slave(child_process, pseudo_terminal))
+}
+
+#[cfg(unix)]
+pub(crate) fn attach_pty(cmd: &mut Command, pseudo_terminal: &PseudoTerminal) {
+    pty::attach(cmd, &pseudo_terminal.pty);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn attach_pty(_cmd: &mut Command, _pseudo_terminal: &PseudoTerminal) {}
+
+#[cfg(unix)]
+pub(crate) fn with_pty_slave(
+    child_process: ChildProcess,
+    pseudo_terminal: &PseudoTerminal,
+) -> ChildProcess {
+    child_process.with_pty_slave(pseudo_terminal.slave_fd())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn with_pty_slave(
+    child_process: ChildProcess,
+    _pseudo_terminal: &PseudoTerminal,
+) -> ChildProcess {
+    child_process
+}
+
+/// Waits (up to [`SOCKET_ACCEPT_TIMEOUT`]) for the child to dial back into `guard`'s socket and
+/// wires the accepted connection up as the `ChildProcess`'s IPC channel. A no-op, leaving
+/// `child_process` without an IPC channel, if `guard` is `None`, the accept times out, or the
+/// platform doesn't support local sockets at all.
+#[cfg(unix)]
+fn connect_socket_channel(
+    child_process: ChildProcess,
+    guard: &Option<SocketGuard>,
+) -> ChildProcess {
+    let Some(guard) = guard else {
+        return child_process;
+    };
+    match guard.accept(SOCKET_ACCEPT_TIMEOUT) {
+        Ok(stream) => child_process.with_ipc_stream(stream),
+        Err(_) => child_process,
+    }
+}
+
+#[cfg(not(unix))]
+fn connect_socket_channel(
+    child_process: ChildProcess,
+    _guard: &Option<SocketGuard>,
+) -> ChildProcess {
+    child_process
+}
+
+fn build_command(
+    command: &str,
+    command_dir: Option<&str>,
+    js_env: Option<&HashMap<String, String>>,
+    exec_argv: Option<&Vec<String>>,
+) -> Command {
+    let (program, args) = os::shell_command(command);
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    if let Some(dir) = command_dir {
+        cmd.current_dir(dir);
+    }
+    if let Some(env) = js_env {
+        cmd.envs(env);
+    }
+    if let Some(exec_argv) = exec_argv {
+        if !exec_argv.is_empty() {
+            cmd.env("NODE_OPTIONS", exec_argv.join(" "));
+        }
+    }
+    cmd
+}
@@ -0,0 +1,24 @@
+/// Quotes a path for safe interpolation into a shell command line on platforms where paths may
+/// contain spaces (notably Windows `cmd.exe`), leaving already-quoted or space-free paths alone.
+pub fn handle_path_space(path: String) -> String {
+    if cfg!(windows) && path.contains(' ') && !path.starts_with('"') {
+        format!("\"{path}\"")
+    } else {
+        path
+    }
+}
+
+/// Returns the `(program, args)` pair that runs `command` through this platform's shell.
+pub fn shell_command(command: &str) -> (String, Vec<String>) {
+    if cfg!(windows) {
+        (
+            "cmd".to_string(),
+            vec!["/C".to_string(), command.to_string()],
+        )
+    } else {
+        (
+            "sh".to_string(),
+            vec!["-c".to_string(), command.to_string()],
+        )
+    }
+}
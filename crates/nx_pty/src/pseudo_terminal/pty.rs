@@ -0,0 +1,54 @@
+use std::io;
+
+/// A pty master/slave pair allocated for one [`super::PseudoTerminal`].
+#[cfg(unix)]
+pub struct Pty {
+    pub master: std::os::fd::RawFd,
+    pub slave: std::os::fd::RawFd,
+}
+
+#[cfg(unix)]
+pub fn open() -> io::Result<Pty> {
+    let mut master: libc::c_int = 0;
+    let mut slave: libc::c_int = 0;
+    let ret = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Pty { master, slave })
+}
+
+/// Wires `cmd`'s stdio to the pty's slave side and makes it the child's controlling terminal
+/// once it calls `setsid` post-fork.
+#[cfg(unix)]
+pub fn attach(cmd: &mut std::process::Command, pty: &Pty) {
+    use std::os::fd::FromRawFd;
+    use std::os::unix::process::CommandExt;
+    use std::process::Stdio;
+
+    let slave = pty.slave;
+    let dup_slave = || unsafe { Stdio::from_raw_fd(libc::dup(slave)) };
+    cmd.stdin(dup_slave());
+    cmd.stdout(dup_slave());
+    cmd.stderr(dup_slave());
+
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::ioctl(slave, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use napi_derive::napi;
+
+use super::child_process::ChildProcess;
+use super::PseudoTerminal;
+
+/// Per-shell command used to source an activation script once the shell's own rc files have
+/// already run, keyed by the shell's executable name.
+#[napi(object)]
+pub struct ShellActivationCommands {
+    pub bash: Option<String>,
+    pub zsh: Option<String>,
+    pub fish: Option<String>,
+    pub pwsh: Option<String>,
+    pub cmd: Option<String>,
+}
+
+fn default_command_for(shell: &str, script: &str) -> String {
+    match shell {
+        "fish" => format!("source {script}"),
+        "pwsh" | "powershell" => format!(". '{script}'"),
+        "cmd" => format!("call \"{script}\""),
+        _ => format!("source {script}"),
+    }
+}
+
+fn activation_command(
+    shell: &str,
+    script: &str,
+    overrides: Option<&ShellActivationCommands>,
+) -> String {
+    let template = overrides.and_then(|o| match shell {
+        "bash" => o.bash.as_deref(),
+        "zsh" => o.zsh.as_deref(),
+        "fish" => o.fish.as_deref(),
+        "pwsh" | "powershell" => o.pwsh.as_deref(),
+        "cmd" => o.cmd.as_deref(),
+        _ => None,
+    });
+    match template {
+        Some(template) => template.replace("{script}", script),
+        None => default_command_for(shell, script),
+    }
+}
+
+/// Launches `shell` as a login/interactive session inside a pseudo-terminal, waits for it to
+/// finish sourcing its own rc files, then injects `activation_script` so environment changes
+/// (e.g. PATH) aren't clobbered by `.bashrc`/`.zshrc`, and hands control to the caller.
+///
+/// Uses a pty expect loop: a sentinel is echoed into the shell and the master is read until that
+/// sentinel comes back, which only happens once the shell has processed its rc files and is
+/// sitting at a prompt. `timeout` bounds how long that wait may take so a hung shell doesn't
+/// block forever.
+#[allow(clippy::too_many_arguments)]
+pub fn shell_session(
+    pseudo_terminal: &PseudoTerminal,
+    shell: String,
+    command_dir: Option<String>,
+    js_env: Option<HashMap<String, String>>,
+    activation_script: String,
+    activation_commands: Option<ShellActivationCommands>,
+    timeout: Option<u32>,
+) -> napi::Result<ChildProcess> {
+    #[cfg(unix)]
+    {
+        run_unix(
+            pseudo_terminal,
+            shell,
+            command_dir,
+            js_env,
+            activation_script,
+            activation_commands,
+            timeout,
+        )
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (
+            pseudo_terminal,
+            shell,
+            command_dir,
+            js_env,
+            activation_script,
+            activation_commands,
+            timeout,
+        );
+        Err(napi::Error::from_reason(
+            "shell_session is not yet supported on this platform".to_string(),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn run_unix(
+    pseudo_terminal: &PseudoTerminal,
+    shell: String,
+    command_dir: Option<String>,
+    js_env: Option<HashMap<String, String>>,
+    activation_script: String,
+    activation_commands: Option<ShellActivationCommands>,
+    timeout: Option<u32>,
+) -> napi::Result<ChildProcess> {
+    use std::os::fd::FromRawFd;
+
+    let shell_name = shell.rsplit('/').next().unwrap_or(&shell).to_string();
+
+    let mut cmd = Command::new(&shell);
+    cmd.arg("-i");
+    if let Some(dir) = &command_dir {
+        cmd.current_dir(dir);
+    }
+    if let Some(env) = &js_env {
+        cmd.envs(env);
+    }
+    super::attach_pty(&mut cmd, pseudo_terminal);
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+    let master = pseudo_terminal.pty().master;
+    // SAFETY: `master` outlives this function via `pseudo_terminal`; we only borrow it for the
+    // duration of the expect loop and never close it through this `File`.
+    let mut master_file = unsafe { std::fs::File::from_raw_fd(libc::dup(master)) };
+
+    // The pty echoes input back to the master the instant it's written, well before the shell
+    // has read (let alone evaluated) it — so a sentinel matched against raw echoed input fires
+    // immediately, before the shell is anywhere near a prompt. Disabling echo for the handshake
+    // means the only occurrence of the sentinel on the master is the `echo` command's own output,
+    // which the line discipline can't produce until the shell actually runs it.
+    let echo_guard = EchoGuard::disable(pseudo_terminal.pty().slave)?;
+
+    let sentinel = format!("__nx_shell_ready_{}_{}", std::process::id(), child.id());
+    master_file
+        .write_all(format!("echo {sentinel}\n").as_bytes())
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+    let ready = wait_for_sentinel(
+        &mut master_file,
+        &sentinel,
+        Duration::from_millis(timeout.unwrap_or(5_000) as u64),
+    );
+    drop(echo_guard);
+    ready?;
+
+    let command = activation_command(
+        &shell_name,
+        &activation_script,
+        activation_commands.as_ref(),
+    );
+    master_file
+        .write_all(format!("{command}\n").as_bytes())
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+    Ok(super::with_pty_slave(
+        ChildProcess::new(child.id()),
+        pseudo_terminal,
+    ))
+}
+
+/// Disables terminal echo on the pty's slave side for as long as it's held, restoring whatever
+/// termios settings were previously in effect when dropped. Operates on the slave (not the
+/// master) since that's the end the line discipline's echo setting actually belongs to.
+#[cfg(unix)]
+struct EchoGuard {
+    fd: std::os::fd::RawFd,
+    previous: libc::termios,
+}
+
+#[cfg(unix)]
+impl EchoGuard {
+    fn disable(fd: std::os::fd::RawFd) -> napi::Result<Self> {
+        let previous = get_termios(fd)?;
+        let mut disabled = previous;
+        disabled.c_lflag &= !libc::ECHO;
+        set_termios(fd, &disabled)?;
+        Ok(Self { fd, previous })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        let _ = set_termios(self.fd, &self.previous);
+    }
+}
+
+#[cfg(unix)]
+fn get_termios(fd: std::os::fd::RawFd) -> napi::Result<libc::termios> {
+    let mut term: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut term) } != 0 {
+        return Err(napi::Error::from_reason(
+            std::io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(term)
+}
+
+#[cfg(unix)]
+fn set_termios(fd: std::os::fd::RawFd, term: &libc::termios) -> napi::Result<()> {
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, term) } != 0 {
+        return Err(napi::Error::from_reason(
+            std::io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Temporarily sets `O_NONBLOCK` on an fd's *open file description*, restoring the previous flags
+/// when dropped.
+///
+/// `master` here is a `dup` of the pty master kept by [`super::PseudoTerminal`], not an
+/// independent fd: `dup` shares the underlying open file description, so file *status* flags
+/// (including `O_NONBLOCK`) are shared with the original master too. Without restoring them, the
+/// real pty master is left permanently non-blocking after this function returns, which surfaces
+/// as a side effect on the `ChildProcess`'s own pty reads.
+#[cfg(unix)]
+struct NonBlockingGuard {
+    fd: std::os::fd::RawFd,
+    previous_flags: libc::c_int,
+}
+
+#[cfg(unix)]
+impl NonBlockingGuard {
+    fn enable(fd: std::os::fd::RawFd) -> napi::Result<Self> {
+        let previous_flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        if previous_flags == -1 {
+            return Err(napi::Error::from_reason(
+                std::io::Error::last_os_error().to_string(),
+            ));
+        }
+        if unsafe { libc::fcntl(fd, libc::F_SETFL, previous_flags | libc::O_NONBLOCK) } == -1 {
+            return Err(napi::Error::from_reason(
+                std::io::Error::last_os_error().to_string(),
+            ));
+        }
+        Ok(Self { fd, previous_flags })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for NonBlockingGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::fcntl(self.fd, libc::F_SETFL, self.previous_flags);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn wait_for_sentinel(
+    master: &mut std::fs::File,
+    sentinel: &str,
+    timeout: Duration,
+) -> napi::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let _non_blocking = NonBlockingGuard::enable(master.as_raw_fd())?;
+
+    let deadline = Instant::now() + timeout;
+    let mut seen = String::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        if seen.contains(sentinel) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(napi::Error::from_reason(format!(
+                "timed out after {timeout:?} waiting for the shell prompt"
+            )));
+        }
+        match master.read(&mut buf) {
+            Ok(0) => {
+                return Err(napi::Error::from_reason(
+                    "shell exited before reaching a prompt".to_string(),
+                ))
+            }
+            Ok(n) => seen.push_str(&String::from_utf8_lossy(&buf[..n])),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Err(e) => return Err(napi::Error::from_reason(e.to_string())),
+        }
+    }
+}
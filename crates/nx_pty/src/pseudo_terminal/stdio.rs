@@ -0,0 +1,66 @@
+use std::process::{Command, Stdio as StdStdio};
+
+use napi_derive::napi;
+
+/// How one stream of a spawned process is wired up.
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdioDisposition {
+    /// Connected to the parent's corresponding stream.
+    Inherit,
+    /// Captured as a readable/writable handle on `ChildProcess`.
+    Piped,
+    /// Connected to the platform null device.
+    Null,
+}
+
+impl From<StdioDisposition> for StdStdio {
+    fn from(disposition: StdioDisposition) -> Self {
+        match disposition {
+            StdioDisposition::Inherit => StdStdio::inherit(),
+            StdioDisposition::Piped => StdStdio::piped(),
+            StdioDisposition::Null => StdStdio::null(),
+        }
+    }
+}
+
+/// Per-stream stdio configuration for [`super::run_command_with_transport`], mirroring the
+/// three-way disposition used by other process runners in place of the coarser `quiet`/`tty`
+/// flags.
+#[napi(object)]
+#[derive(Debug, Clone, Copy)]
+pub struct StdioConfig {
+    pub stdin: Option<StdioDisposition>,
+    pub stdout: Option<StdioDisposition>,
+    pub stderr: Option<StdioDisposition>,
+}
+
+/// Builds the [`StdioConfig`] that `quiet` maps onto when the caller doesn't supply an explicit
+/// one: stdout/stderr go to the null device so the child's output is suppressed, same as `quiet`
+/// meant historically, while stdin is left at its default.
+pub fn from_quiet(quiet: Option<bool>) -> StdioConfig {
+    let disposition = if quiet.unwrap_or(false) {
+        Some(StdioDisposition::Null)
+    } else {
+        None
+    };
+    StdioConfig {
+        stdin: None,
+        stdout: disposition,
+        stderr: disposition,
+    }
+}
+
+/// Wires `cmd`'s three streams according to `config`, defaulting any unset stream to
+/// [`StdioDisposition::Inherit`].
+pub fn apply(cmd: &mut Command, config: &StdioConfig) {
+    cmd.stdin(StdStdio::from(
+        config.stdin.unwrap_or(StdioDisposition::Inherit),
+    ));
+    cmd.stdout(StdStdio::from(
+        config.stdout.unwrap_or(StdioDisposition::Inherit),
+    ));
+    cmd.stderr(StdStdio::from(
+        config.stderr.unwrap_or(StdioDisposition::Inherit),
+    ));
+}
@@ -0,0 +1,128 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::child_process::Transport;
+
+/// Generates an OS-appropriate local-socket name for one process: a Unix domain socket path
+/// under `/tmp` (mixing the command and the current time into the name so it stays unique and
+/// comfortably under the ~100-char `sun_path` limit), or a Windows named-pipe path.
+fn socket_name(command: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    command.hash(&mut hasher);
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .hash(&mut hasher);
+    let hash = hasher.finish();
+    let pid = std::process::id();
+
+    if cfg!(windows) {
+        format!(r"\\.\pipe\nx.{pid:x}.{hash:x}")
+    } else {
+        format!("/tmp/nx.{pid:x}.{hash:x}.sock")
+    }
+}
+
+/// Owns the listening end of a local-socket transport for as long as the owning
+/// [`super::child_process::ChildProcess`] is alive, so the socket stays bound and accepting for
+/// the life of the child instead of being dropped (and left orphaned on disk) the instant it's
+/// created. The child is expected to dial `NX_PTY_SOCKET_PATH` and speak the same length-prefixed
+/// frame protocol as [`super::ipc`] once connected; see [`Self::accept`].
+#[cfg(unix)]
+pub struct SocketGuard {
+    name: String,
+    listener: std::os::unix::net::UnixListener,
+}
+
+#[cfg(unix)]
+impl SocketGuard {
+    /// Blocks, up to `timeout`, for the child to connect, handing back the accepted stream so
+    /// control traffic can flow over it instead of stdio. Polls rather than blocking indefinitely
+    /// so a child that never dials in (e.g. one that doesn't speak the protocol) doesn't hang the
+    /// caller forever.
+    pub fn accept(
+        &self,
+        timeout: std::time::Duration,
+    ) -> std::io::Result<std::os::unix::net::UnixStream> {
+        self.listener.set_nonblocking(true)?;
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _addr)) => {
+                    stream.set_nonblocking(false)?;
+                    return Ok(stream);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if std::time::Instant::now() >= deadline {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            "timed out waiting for the child to connect to the local socket",
+                        ));
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SocketGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.name);
+    }
+}
+
+/// Socket transport isn't supported on this platform yet: there's no real listener to hold, so
+/// [`attach`] always falls back to [`Transport::Stdio`] here (see the Windows `bind` below).
+#[cfg(not(unix))]
+pub struct SocketGuard;
+
+/// Attempts to wire `cmd`'s control/IPC traffic through a local socket instead of stdio, leaving
+/// the child's own stdin/stdout/stderr free (e.g. for a task that draws its own terminal UI).
+/// Falls back to [`Transport::Stdio`] when `requested` is false or binding the socket fails. The
+/// returned [`SocketGuard`], when present, must be kept alive for as long as the socket should
+/// keep listening.
+pub fn attach(
+    cmd: &mut Command,
+    command: &str,
+    requested: bool,
+) -> (Transport, Option<SocketGuard>) {
+    if !requested {
+        return (Transport::Stdio, None);
+    }
+    match bind(&socket_name(command)) {
+        Ok((name, guard)) => {
+            cmd.env("NX_PTY_SOCKET_PATH", &name);
+            (Transport::Socket(name), guard)
+        }
+        Err(_) => (Transport::Stdio, None),
+    }
+}
+
+#[cfg(unix)]
+fn bind(name: &str) -> std::io::Result<(String, Option<SocketGuard>)> {
+    let listener = std::os::unix::net::UnixListener::bind(name)?;
+    Ok((
+        name.to_string(),
+        Some(SocketGuard {
+            name: name.to_string(),
+            listener,
+        }),
+    ))
+}
+
+#[cfg(windows)]
+fn bind(_name: &str) -> std::io::Result<(String, Option<SocketGuard>)> {
+    // A real named-pipe server needs repeated `ConnectNamedPipe` calls, which std doesn't expose.
+    // Rather than claim a transport that can't actually accept a connection, fail here so `attach`
+    // transparently falls back to `Transport::Stdio`, same as any other bind failure.
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "local-socket transport is not yet supported on Windows",
+    ))
+}
@@ -4,9 +4,11 @@ use tracing::trace;
 
 use nx_logger::enable_logger;
 use nx_pty::pseudo_terminal::child_process::ChildProcess;
-use nx_pty::pseudo_terminal::{create_pseudo_terminal, os, run_command};
-
-
+use nx_pty::pseudo_terminal::shell_session::{shell_session, ShellActivationCommands};
+use nx_pty::pseudo_terminal::stdio::StdioConfig;
+use nx_pty::pseudo_terminal::{
+    create_pseudo_terminal, fork_with_transport, os, run_command_with_transport,
+};
 
 pub struct RustPseudoTerminal {}
 
@@ -16,6 +18,14 @@ impl RustPseudoTerminal {
         Ok(Self {})
     }
 
+    /// `local_socket`, when `true`, routes control/IPC traffic over a per-process local socket
+    /// instead of stdio, leaving the child's own stdin/stdout/stderr free — useful for
+    /// interactive tasks that want to draw their own terminal UI. Falls back to stdio if the
+    /// socket can't be bound; check `ChildProcess::transport` to see which one was used.
+    /// `stdio`, when given, independently sets each of stdin/stdout/stderr to inherit/piped/null
+    /// instead of the default behavior driven by `quiet`/`tty`: `tty` (default `true`) runs on
+    /// the pty, otherwise `quiet` maps onto stdout/stderr going to the null device.
+    #[allow(clippy::too_many_arguments)]
     pub fn run_command(
         &self,
         command: String,
@@ -24,9 +34,11 @@ impl RustPseudoTerminal {
         exec_argv: Option<Vec<String>>,
         quiet: Option<bool>,
         tty: Option<bool>,
+        local_socket: Option<bool>,
+        stdio: Option<StdioConfig>,
     ) -> napi::Result<ChildProcess> {
         let pseudo_terminal = create_pseudo_terminal()?;
-        run_command(
+        run_command_with_transport(
             &pseudo_terminal,
             command,
             command_dir,
@@ -34,11 +46,19 @@ impl RustPseudoTerminal {
             exec_argv,
             quiet,
             tty,
+            local_socket,
+            stdio,
         )
     }
 
-    /// This allows us to run a pseudoterminal with a fake node ipc channel
-    /// this makes it possible to be backwards compatible with the old implementation
+    /// Forks `fork_script` with a typed, framed duplex channel to the child in place of the
+    /// legacy fake node IPC shim: `pseudo_ipc_path` is no longer wired up as a file for the
+    /// child to poll, instead a pair of unnamed pipes is created at fork time and their
+    /// descriptors are handed to the child via its environment. Kept as a thin wrapper with the
+    /// old signature (`pseudo_ipc_path` and `quiet` included) so existing callers don't need to
+    /// change; `quiet` is presently unused by the new channel, same as before. `local_socket`
+    /// mirrors the option on [`Self::run_command`], using a local socket instead of the pipe
+    /// channel for the fork's control traffic.
     #[allow(clippy::too_many_arguments)]
     pub fn fork(
         &self,
@@ -49,22 +69,46 @@ impl RustPseudoTerminal {
         js_env: Option<HashMap<String, String>>,
         exec_argv: Option<Vec<String>>,
         quiet: bool,
+        local_socket: Option<bool>,
     ) -> napi::Result<ChildProcess> {
-        let command = format!(
-            "node {} {} {}",
-            os::handle_path_space(fork_script),
-            pseudo_ipc_path,
-            id
-        );
+        let _ = (pseudo_ipc_path, quiet);
+        let command = format!("node {} {}", os::handle_path_space(fork_script), id);
 
         trace!("nx_fork command: {}", &command);
-        self.run_command(
+        let pseudo_terminal = create_pseudo_terminal()?;
+        fork_with_transport(
+            &pseudo_terminal,
             command,
             command_dir,
             js_env,
             exec_argv,
-            Some(quiet),
-            Some(true),
+            local_socket,
+        )
+    }
+
+    /// Launches `shell` as an interactive session and, once it's finished sourcing its own rc
+    /// files (`.bashrc`/`.zshrc`/etc.), injects `activation_script` so PATH/env changes survive.
+    /// `activation_commands` overrides the default per-shell command used to source it; `timeout`
+    /// (in milliseconds, default 5000) bounds how long we wait for the shell's first prompt.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shell_session(
+        &self,
+        shell: String,
+        command_dir: Option<String>,
+        js_env: Option<HashMap<String, String>>,
+        activation_script: String,
+        activation_commands: Option<ShellActivationCommands>,
+        timeout: Option<u32>,
+    ) -> napi::Result<ChildProcess> {
+        let pseudo_terminal = create_pseudo_terminal()?;
+        shell_session(
+            &pseudo_terminal,
+            shell,
+            command_dir,
+            js_env,
+            activation_script,
+            activation_commands,
+            timeout,
         )
     }
 }